@@ -1,4 +1,6 @@
-//! WASM kernels for Dense, MLP, and recurrent forward passes.
+//! WASM kernels for Dense, MLP, and recurrent forward passes, plus
+//! Q8_0 block-quantized variants for lower-memory genome evaluation and
+//! `_mt` variants that split a batch across wasm-threads workers.
 
 use core::arch::wasm32::*;
 use core::mem;
@@ -14,6 +16,16 @@ fn to_usize(value: i32) -> usize {
 
 /// Compute a SIMD-accelerated dot product.
 ///
+/// Uses four independent `f32x4` accumulators over 16-wide chunks so the
+/// reduction isn't latency-bound on wide inputs (large hidden layers):
+/// each accumulator only depends on its own previous iteration, so all four
+/// can be in flight at once. They're summed once at the end, before the
+/// existing 4-wide and scalar tails handle the remainder.
+///
+/// With the `relaxed-simd` feature enabled and a `relaxed-simd` target
+/// feature available, each multiply-add fuses into a single relaxed madd
+/// instead of a separate multiply and add.
+///
 /// # Safety
 ///
 /// Pointers must be valid for `in_size` reads.
@@ -21,13 +33,49 @@ fn to_usize(value: i32) -> usize {
 #[inline]
 unsafe fn dense_dot(weights_ptr: *const f32, input_ptr: *const f32, in_size: usize) -> f32 {
     let mut i = 0usize;
-    let mut sum = f32x4_splat(0.0);
+    let mut acc0 = f32x4_splat(0.0);
+    let mut acc1 = f32x4_splat(0.0);
+    let mut acc2 = f32x4_splat(0.0);
+    let mut acc3 = f32x4_splat(0.0);
     // Safety: Caller guarantees pointers are valid for in_size
     unsafe {
+        while i + 16 <= in_size {
+            let w0 = v128_load(weights_ptr.add(i) as *const v128);
+            let x0 = v128_load(input_ptr.add(i) as *const v128);
+            let w1 = v128_load(weights_ptr.add(i + 4) as *const v128);
+            let x1 = v128_load(input_ptr.add(i + 4) as *const v128);
+            let w2 = v128_load(weights_ptr.add(i + 8) as *const v128);
+            let x2 = v128_load(input_ptr.add(i + 8) as *const v128);
+            let w3 = v128_load(weights_ptr.add(i + 12) as *const v128);
+            let x3 = v128_load(input_ptr.add(i + 12) as *const v128);
+            #[cfg(all(feature = "relaxed-simd", target_feature = "relaxed-simd"))]
+            {
+                acc0 = f32x4_relaxed_madd(w0, x0, acc0);
+                acc1 = f32x4_relaxed_madd(w1, x1, acc1);
+                acc2 = f32x4_relaxed_madd(w2, x2, acc2);
+                acc3 = f32x4_relaxed_madd(w3, x3, acc3);
+            }
+            #[cfg(not(all(feature = "relaxed-simd", target_feature = "relaxed-simd")))]
+            {
+                acc0 = f32x4_add(acc0, f32x4_mul(w0, x0));
+                acc1 = f32x4_add(acc1, f32x4_mul(w1, x1));
+                acc2 = f32x4_add(acc2, f32x4_mul(w2, x2));
+                acc3 = f32x4_add(acc3, f32x4_mul(w3, x3));
+            }
+            i += 16;
+        }
+        let mut sum = f32x4_add(f32x4_add(acc0, acc1), f32x4_add(acc2, acc3));
         while i + 4 <= in_size {
             let w = v128_load(weights_ptr.add(i) as *const v128);
             let x = v128_load(input_ptr.add(i) as *const v128);
-            sum = f32x4_add(sum, f32x4_mul(w, x));
+            #[cfg(all(feature = "relaxed-simd", target_feature = "relaxed-simd"))]
+            {
+                sum = f32x4_relaxed_madd(w, x, sum);
+            }
+            #[cfg(not(all(feature = "relaxed-simd", target_feature = "relaxed-simd")))]
+            {
+                sum = f32x4_add(sum, f32x4_mul(w, x));
+            }
             i += 4;
         }
         let mut total = f32x4_extract_lane::<0>(sum)
@@ -44,6 +92,10 @@ unsafe fn dense_dot(weights_ptr: *const f32, input_ptr: *const f32, in_size: usi
 
 /// Compute a SIMD-accelerated dot product with two inputs multiplied together.
 ///
+/// With the `relaxed-simd` feature enabled and a `relaxed-simd` target
+/// feature available, `w*(a*b)` fuses into a relaxed madd instead of a
+/// separate multiply and add.
+///
 /// # Safety
 ///
 /// Pointers must be valid for `len` reads.
@@ -63,7 +115,14 @@ unsafe fn dense_dot_mul(
             let a = v128_load(a_ptr.add(i) as *const v128);
             let b = v128_load(b_ptr.add(i) as *const v128);
             let ab = f32x4_mul(a, b);
-            sum = f32x4_add(sum, f32x4_mul(w, ab));
+            #[cfg(all(feature = "relaxed-simd", target_feature = "relaxed-simd"))]
+            {
+                sum = f32x4_relaxed_madd(w, ab, sum);
+            }
+            #[cfg(not(all(feature = "relaxed-simd", target_feature = "relaxed-simd")))]
+            {
+                sum = f32x4_add(sum, f32x4_mul(w, ab));
+            }
             i += 4;
         }
         let mut total = f32x4_extract_lane::<0>(sum)
@@ -84,8 +143,455 @@ fn sigmoid(x: f32) -> f32 {
     1.0 / (1.0 + (-x).exp())
 }
 
+/// Hyperbolic tangent activation, selected by `ACTIVATION_TANH`.
+pub const ACTIVATION_TANH: i32 = 0;
+/// Sigmoid activation, selected by `ACTIVATION_SIGMOID`.
+pub const ACTIVATION_SIGMOID: i32 = 1;
+/// Rectified linear unit, selected by `ACTIVATION_RELU`.
+pub const ACTIVATION_RELU: i32 = 2;
+/// Leaky ReLU with a fixed 0.01 negative slope, selected by `ACTIVATION_LEAKY_RELU`.
+pub const ACTIVATION_LEAKY_RELU: i32 = 3;
+/// GELU (tanh approximation), selected by `ACTIVATION_GELU`.
+pub const ACTIVATION_GELU: i32 = 4;
+/// Softsign (`x / (1 + |x|)`), selected by `ACTIVATION_SOFTSIGN`.
+pub const ACTIVATION_SOFTSIGN: i32 = 5;
+/// Identity (no-op), selected by `ACTIVATION_IDENTITY`.
+pub const ACTIVATION_IDENTITY: i32 = 6;
+
+/// Dispatch to the activation function selected by `kind` (one of the
+/// `ACTIVATION_*` constants). Unrecognized values fall back to `tanh`.
+#[inline]
+fn apply_activation(kind: i32, x: f32) -> f32 {
+    match kind {
+        ACTIVATION_SIGMOID => sigmoid(x),
+        ACTIVATION_RELU => x.max(0.0),
+        ACTIVATION_LEAKY_RELU => {
+            if x >= 0.0 {
+                x
+            } else {
+                0.01 * x
+            }
+        }
+        ACTIVATION_GELU => 0.5 * x * (1.0 + (0.79788456 * (x + 0.044715 * x * x * x)).tanh()),
+        ACTIVATION_SOFTSIGN => x / (1.0 + x.abs()),
+        ACTIVATION_IDENTITY => x,
+        _ => x.tanh(),
+    }
+}
+
+/// Spin at a sense-reversing atomic barrier shared by `thread_count` workers,
+/// safe to call again on the next round with the same buffer.
+///
+/// `barrier_ptr[0]` is the arrival counter and `barrier_ptr[1]` is a
+/// generation counter. Each worker snapshots the generation before it
+/// arrives; the last arrival resets the counter and bumps the generation,
+/// and every other worker spins until it observes a generation different
+/// from the one it snapshotted. Bumping rather than resetting the
+/// generation means a worker that laps ahead into the next round can't
+/// be mistaken for the round it's actually waiting on, unlike resetting a
+/// single counter to a fixed value. Modeled on ggml's compute-graph
+/// threading: cheap for the short waits typical of one forward pass,
+/// avoiding the syscall overhead of a blocking wait.
+///
+/// # Safety
+///
+/// `barrier_ptr` must point to two contiguous `i32` values that all
+/// `thread_count` workers share and use only for this barrier, both
+/// zero-initialized before the first round.
+#[inline]
+unsafe fn spin_barrier(barrier_ptr: *mut i32, thread_count: i32) {
+    use core::sync::atomic::{AtomicI32, Ordering};
+    // Safety: caller guarantees barrier_ptr is valid for two i32s shared among thread_count workers
+    let counter = unsafe { &*(barrier_ptr as *const AtomicI32) };
+    let generation = unsafe { &*(barrier_ptr.add(1) as *const AtomicI32) };
+    let local_generation = generation.load(Ordering::Acquire);
+    let arrived = counter.fetch_add(1, Ordering::AcqRel) + 1;
+    if arrived == thread_count {
+        counter.store(0, Ordering::Release);
+        generation.fetch_add(1, Ordering::AcqRel);
+    } else {
+        while generation.load(Ordering::Acquire) == local_generation {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Number of quantized values packed into one Q8_0 block.
+const Q8_BLOCK_SIZE: usize = 32;
+
+/// Byte size of one Q8_0 block: one `f32` scale followed by 32 `i8` quants.
+const Q8_BLOCK_BYTES: usize = mem::size_of::<f32>() + Q8_BLOCK_SIZE;
+
+/// Number of Q8_0 blocks needed to cover `len` values.
+#[inline]
+fn q8_block_count(len: usize) -> usize {
+    len.div_ceil(Q8_BLOCK_SIZE)
+}
+
+/// Quantize up to `Q8_BLOCK_SIZE` values from `src` into a single Q8_0 block,
+/// zero-padding any remainder, and return the block's scale `d`.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` reads (`len <= Q8_BLOCK_SIZE`).
+/// `q_out` must be valid for `Q8_BLOCK_SIZE` writes.
+#[inline]
+unsafe fn quantize_q8_block(src: *const f32, len: usize, q_out: *mut i8) -> f32 {
+    let mut block = [0.0f32; Q8_BLOCK_SIZE];
+    // Safety: caller guarantees src is valid for len <= Q8_BLOCK_SIZE reads
+    unsafe {
+        for i in 0..len {
+            block[i] = *src.add(i);
+        }
+    }
+    let mut max_abs = 0.0f32;
+    for &v in &block {
+        let a = v.abs();
+        if a > max_abs {
+            max_abs = a;
+        }
+    }
+    if max_abs == 0.0 {
+        // Safety: caller guarantees q_out is valid for Q8_BLOCK_SIZE writes
+        unsafe {
+            for i in 0..Q8_BLOCK_SIZE {
+                *q_out.add(i) = 0;
+            }
+        }
+        return 0.0;
+    }
+    let d = max_abs / 127.0;
+    let inv_d = 1.0 / d;
+    // Safety: caller guarantees q_out is valid for Q8_BLOCK_SIZE writes
+    unsafe {
+        for i in 0..Q8_BLOCK_SIZE {
+            *q_out.add(i) = (block[i] * inv_d).round() as i8;
+        }
+    }
+    d
+}
+
+/// Quantize a length-`len` row into consecutive Q8_0 blocks, writing the
+/// packed `i8` quants to `q_out` and the per-block scales to `scale_out`.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` reads. `q_out` must be valid for
+/// `q8_block_count(len) * Q8_BLOCK_SIZE` writes, `scale_out` for
+/// `q8_block_count(len)` writes.
+unsafe fn quantize_row_q8(src: *const f32, len: usize, q_out: *mut i8, scale_out: *mut f32) {
+    let n_blocks = q8_block_count(len);
+    let mut remaining = len;
+    // Safety: caller guarantees buffer sizes match q8_block_count(len)
+    unsafe {
+        for blk in 0..n_blocks {
+            let take = remaining.min(Q8_BLOCK_SIZE);
+            let d = quantize_q8_block(src.add(blk * Q8_BLOCK_SIZE), take, q_out.add(blk * Q8_BLOCK_SIZE));
+            *scale_out.add(blk) = d;
+            remaining -= take;
+        }
+    }
+}
+
+/// Compute a Q8_0 block-quantized dot product between a weight row (packed
+/// as `n_blocks` Q8_0 blocks) and a pre-quantized input row.
+///
+/// # Safety
+///
+/// `weight_blocks_ptr` must be valid for `n_blocks * Q8_BLOCK_BYTES` reads.
+/// `q_x` must be valid for `n_blocks * Q8_BLOCK_SIZE` reads, `dx` for `n_blocks` reads.
+#[inline]
+unsafe fn dense_dot_q8(
+    weight_blocks_ptr: *const u8,
+    q_x: *const i8,
+    dx: *const f32,
+    n_blocks: usize,
+) -> f32 {
+    let mut total = 0.0f32;
+    // Safety: caller guarantees pointers are valid for n_blocks blocks
+    unsafe {
+        for blk in 0..n_blocks {
+            let block_ptr = weight_blocks_ptr.add(blk * Q8_BLOCK_BYTES);
+            let d = *(block_ptr as *const f32);
+            let qw_ptr = block_ptr.add(mem::size_of::<f32>()) as *const i8;
+            let qx_ptr = q_x.add(blk * Q8_BLOCK_SIZE);
+
+            let w0 = v128_load(qw_ptr as *const v128);
+            let w1 = v128_load(qw_ptr.add(16) as *const v128);
+            let x0 = v128_load(qx_ptr as *const v128);
+            let x1 = v128_load(qx_ptr.add(16) as *const v128);
+
+            let mut acc = i32x4_splat(0);
+            acc = i32x4_add(
+                acc,
+                i32x4_dot_i16x8(i16x8_extend_low_i8x16(w0), i16x8_extend_low_i8x16(x0)),
+            );
+            acc = i32x4_add(
+                acc,
+                i32x4_dot_i16x8(i16x8_extend_high_i8x16(w0), i16x8_extend_high_i8x16(x0)),
+            );
+            acc = i32x4_add(
+                acc,
+                i32x4_dot_i16x8(i16x8_extend_low_i8x16(w1), i16x8_extend_low_i8x16(x1)),
+            );
+            acc = i32x4_add(
+                acc,
+                i32x4_dot_i16x8(i16x8_extend_high_i8x16(w1), i16x8_extend_high_i8x16(x1)),
+            );
+            let isum = i32x4_extract_lane::<0>(acc)
+                + i32x4_extract_lane::<1>(acc)
+                + i32x4_extract_lane::<2>(acc)
+                + i32x4_extract_lane::<3>(acc);
+
+            total += d * *dx.add(blk) * (isum as f32);
+        }
+    }
+    total
+}
+
+/// Number of quantized values packed into one Q4_1 block.
+const Q4_1_BLOCK_SIZE: usize = 32;
+
+/// Byte size of one Q4_1 block: `d` (f32) + `m` (f32) + precomputed quant-sum
+/// (f32), followed by 32 packed 4-bit quants (two per byte).
+const Q4_1_BLOCK_BYTES: usize = 3 * mem::size_of::<f32>() + Q4_1_BLOCK_SIZE / 2;
+
+/// Number of Q4_1 blocks needed to cover `len` values.
+#[inline]
+fn q4_1_block_count(len: usize) -> usize {
+    len.div_ceil(Q4_1_BLOCK_SIZE)
+}
+
+/// Compute the Q4_1 scale `d`, minimum `m`, per-value quants, and their sum
+/// for one zero-padded block of up to `Q4_1_BLOCK_SIZE` values.
+fn q4_1_quantize(block: &[f32; Q4_1_BLOCK_SIZE]) -> (f32, f32, [u8; Q4_1_BLOCK_SIZE], f32) {
+    let mut min = block[0];
+    let mut max = block[0];
+    for &v in &block[1..] {
+        if v < min {
+            min = v;
+        }
+        if v > max {
+            max = v;
+        }
+    }
+    let d = if max > min { (max - min) / 15.0 } else { 0.0 };
+    let mut quants = [0u8; Q4_1_BLOCK_SIZE];
+    let mut sum_q = 0.0f32;
+    if d > 0.0 {
+        let inv_d = 1.0 / d;
+        for i in 0..Q4_1_BLOCK_SIZE {
+            let q = ((block[i] - min) * inv_d).round().clamp(0.0, 15.0) as u8;
+            quants[i] = q;
+            sum_q += q as f32;
+        }
+    }
+    (d, min, quants, sum_q)
+}
+
+/// Quantize up to `Q4_1_BLOCK_SIZE` values from `src` into a single packed
+/// Q4_1 block (two 4-bit quants per byte), returning `(d, m, sum_q)`.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` reads (`len <= Q4_1_BLOCK_SIZE`).
+/// `packed_out` must be valid for `Q4_1_BLOCK_SIZE / 2` writes.
+unsafe fn quantize_q4_1_block(src: *const f32, len: usize, packed_out: *mut u8) -> (f32, f32, f32) {
+    let mut block = [0.0f32; Q4_1_BLOCK_SIZE];
+    // Safety: caller guarantees src is valid for len <= Q4_1_BLOCK_SIZE reads
+    unsafe {
+        for i in 0..len {
+            block[i] = *src.add(i);
+        }
+    }
+    let (d, m, quants, sum_q) = q4_1_quantize(&block);
+    // Safety: caller guarantees packed_out is valid for Q4_1_BLOCK_SIZE / 2 writes
+    unsafe {
+        for i in 0..(Q4_1_BLOCK_SIZE / 2) {
+            *packed_out.add(i) = quants[2 * i] | (quants[2 * i + 1] << 4);
+        }
+    }
+    (d, m, sum_q)
+}
+
+/// Quantize up to `Q4_1_BLOCK_SIZE` values from `src` into unpacked,
+/// one-byte-per-quant form. Used for the transient per-inference input row,
+/// which has no need for the weight side's 4-bit packing.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` reads (`len <= Q4_1_BLOCK_SIZE`).
+/// `q_out` must be valid for `Q4_1_BLOCK_SIZE` writes.
+unsafe fn quantize_q4_1_block_unpacked(src: *const f32, len: usize, q_out: *mut u8) -> (f32, f32, f32) {
+    let mut block = [0.0f32; Q4_1_BLOCK_SIZE];
+    // Safety: caller guarantees src is valid for len <= Q4_1_BLOCK_SIZE reads
+    unsafe {
+        for i in 0..len {
+            block[i] = *src.add(i);
+        }
+    }
+    let (d, m, quants, sum_q) = q4_1_quantize(&block);
+    // Safety: caller guarantees q_out is valid for Q4_1_BLOCK_SIZE writes
+    unsafe {
+        for i in 0..Q4_1_BLOCK_SIZE {
+            *q_out.add(i) = quants[i];
+        }
+    }
+    (d, m, sum_q)
+}
+
+/// Quantize a length-`len` row into consecutive unpacked Q4_1 blocks.
+///
+/// # Safety
+///
+/// `src` must be valid for `len` reads. `q_out` must be valid for
+/// `q4_1_block_count(len) * Q4_1_BLOCK_SIZE` writes, `d_out`/`m_out`/`sum_out`
+/// for `q4_1_block_count(len)` writes each.
+unsafe fn quantize_row_q4_1_unpacked(
+    src: *const f32,
+    len: usize,
+    q_out: *mut u8,
+    d_out: *mut f32,
+    m_out: *mut f32,
+    sum_out: *mut f32,
+) {
+    let n_blocks = q4_1_block_count(len);
+    let mut remaining = len;
+    // Safety: caller guarantees buffer sizes match q4_1_block_count(len)
+    unsafe {
+        for blk in 0..n_blocks {
+            let take = remaining.min(Q4_1_BLOCK_SIZE);
+            let (d, m, sum_q) = quantize_q4_1_block_unpacked(
+                src.add(blk * Q4_1_BLOCK_SIZE),
+                take,
+                q_out.add(blk * Q4_1_BLOCK_SIZE),
+            );
+            *d_out.add(blk) = d;
+            *m_out.add(blk) = m;
+            *sum_out.add(blk) = sum_q;
+            remaining -= take;
+        }
+    }
+}
+
+/// Unpack the 4-bit quant at index `i` (0..Q4_1_BLOCK_SIZE) from a packed Q4_1 block.
+#[inline]
+unsafe fn unpack_q4_1(packed_ptr: *const u8, i: usize) -> u8 {
+    // Safety: caller guarantees packed_ptr is valid for i / 2 + 1 reads
+    let byte = unsafe { *packed_ptr.add(i / 2) };
+    if i % 2 == 0 {
+        byte & 0x0F
+    } else {
+        byte >> 4
+    }
+}
+
+/// Compute a Q4_1 block-quantized dot product between a weight row (packed
+/// as `n_blocks` Q4_1 blocks with precomputed quant-sums) and a
+/// pre-quantized, unpacked input row. Reuses each block's stored `sum_qw`
+/// and the input's `sum_qx` rather than re-summing 32 quants per dot product.
+///
+/// # Safety
+///
+/// `weight_blocks_ptr` must be valid for `n_blocks * Q4_1_BLOCK_BYTES` reads.
+/// `q_x` must be valid for `n_blocks * Q4_1_BLOCK_SIZE` reads; `dx`/`mx`/`sum_qx`
+/// for `n_blocks` reads each.
+#[inline]
+unsafe fn dense_dot_q4_1(
+    weight_blocks_ptr: *const u8,
+    q_x: *const u8,
+    dx: *const f32,
+    mx: *const f32,
+    sum_qx: *const f32,
+    n_blocks: usize,
+) -> f32 {
+    let mut total = 0.0f32;
+    // Safety: caller guarantees pointers are valid for n_blocks blocks
+    unsafe {
+        for blk in 0..n_blocks {
+            let block_ptr = weight_blocks_ptr.add(blk * Q4_1_BLOCK_BYTES);
+            let d = *(block_ptr as *const f32);
+            let m = *(block_ptr.add(mem::size_of::<f32>()) as *const f32);
+            let sum_qw = *(block_ptr.add(2 * mem::size_of::<f32>()) as *const f32);
+            let packed_ptr = block_ptr.add(3 * mem::size_of::<f32>());
+            let qx_ptr = q_x.add(blk * Q4_1_BLOCK_SIZE);
+
+            let dx_blk = *dx.add(blk);
+            let mx_blk = *mx.add(blk);
+            let sum_qx_blk = *sum_qx.add(blk);
+
+            let mut sum_qwqx = 0.0f32;
+            for i in 0..Q4_1_BLOCK_SIZE {
+                let qw = unpack_q4_1(packed_ptr, i) as f32;
+                let qx = *qx_ptr.add(i) as f32;
+                sum_qwqx += qw * qx;
+            }
+
+            total += d * dx_blk * sum_qwqx
+                + m * dx_blk * sum_qx_blk
+                + d * mx_blk * sum_qw
+                + m * mx_blk * (Q4_1_BLOCK_SIZE as f32);
+        }
+    }
+    total
+}
+
+/// Quantize a Dense-style weight matrix (row-major, each row `in_size`
+/// weights followed by one `f32` bias) into Q4_1 blocks for use with
+/// `dense_forward_q4_1`.
+///
+/// # Safety
+///
+/// `weights_ptr` must be valid for `out_size * (in_size + 1)` reads.
+/// `out_ptr` must be valid for
+/// `out_size * (q4_1_block_count(in_size) * Q4_1_BLOCK_BYTES + size_of::<f32>())` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn quantize_weights_q4_1(
+    weights_ptr: *const f32,
+    out_ptr: *mut u8,
+    in_size: i32,
+    out_size: i32,
+) {
+    if weights_ptr.is_null() || out_ptr.is_null() {
+        return;
+    }
+    let in_size = to_usize(in_size);
+    let out_size = to_usize(out_size);
+    let n_blocks = q4_1_block_count(in_size);
+    let packed_row_bytes = n_blocks * Q4_1_BLOCK_BYTES;
+    let out_row_bytes = packed_row_bytes + mem::size_of::<f32>();
+    let in_row_floats = in_size + 1;
+
+    // Safety: Pointers are checked generally, but specific bounds are caller's responsibility.
+    unsafe {
+        for o in 0..out_size {
+            let src_row = weights_ptr.add(o * in_row_floats);
+            let dst_row = out_ptr.add(o * out_row_bytes);
+            let mut remaining = in_size;
+            for blk in 0..n_blocks {
+                let take = remaining.min(Q4_1_BLOCK_SIZE);
+                let block_ptr = dst_row.add(blk * Q4_1_BLOCK_BYTES);
+                let (d, m, sum_q) = quantize_q4_1_block(
+                    src_row.add(blk * Q4_1_BLOCK_SIZE),
+                    take,
+                    block_ptr.add(3 * mem::size_of::<f32>()),
+                );
+                *(block_ptr as *mut f32) = d;
+                *(block_ptr.add(mem::size_of::<f32>()) as *mut f32) = m;
+                *(block_ptr.add(2 * mem::size_of::<f32>()) as *mut f32) = sum_q;
+                remaining -= take;
+            }
+            let bias = *src_row.add(in_size);
+            *(dst_row.add(packed_row_bytes) as *mut f32) = bias;
+        }
+    }
+}
+
 /// Compute a Dense forward pass for a batch of inputs.
 ///
+/// `activation` selects the output activation via one of the `ACTIVATION_*`
+/// constants (defaults to `tanh` for unrecognized values).
+///
 /// # Safety
 ///
 /// Pointers must be valid for the specified dimensions and strides.
@@ -100,6 +606,7 @@ pub unsafe extern "C" fn dense_forward(
     batch_count: i32,
     input_stride: i32,
     output_stride: i32,
+    activation: i32,
 ) {
     if weights_ptr.is_null() || input_ptr.is_null() || output_ptr.is_null() {
         return;
@@ -128,115 +635,677 @@ pub unsafe extern "C" fn dense_forward(
                 let sum = dense_dot(weights_ptr.add(w_index), input_ptr.add(input_base), in_size);
                 let bias = *weights_ptr.add(w_index + in_size);
                 w_index += in_size + 1;
-                *output_ptr.add(output_base + o) = (sum + bias).tanh();
+                *output_ptr.add(output_base + o) = apply_activation(activation, sum + bias);
             }
         }
     }
 }
 
-/// Compute an MLP forward pass for a batch of inputs.
+/// Threaded variant of `dense_forward`: worker `thread_id` (of `thread_count`
+/// total, numbered `0..thread_count`) processes batch indices
+/// `thread_id, thread_id + thread_count, ...`, each writing a disjoint
+/// `output_stride`-sized slice of `output_ptr`, so no locking is needed
+/// beyond the closing barrier. Call this from every worker with the same
+/// `atomics_ptr` (two zero-initialized, shared `i32` values); all workers
+/// return only once every worker has finished writing its slice, and the
+/// same buffer may be reused for the next round.
 ///
 /// # Safety
 ///
-/// Pointers must be valid. `scratch_ptr` must point to sufficient scratch memory.
-/// `layer_sizes_ptr` must point to `layer_count` integers.
+/// Same requirements as `dense_forward`, plus: `thread_id` must be in
+/// `0..thread_count`, and `atomics_ptr` must point to two contiguous `i32`
+/// values shared by and reserved for exactly these `thread_count` workers.
 #[no_mangle]
-pub unsafe extern "C" fn mlp_forward(
+pub unsafe extern "C" fn dense_forward_mt(
     weights_ptr: *const f32,
-    layer_sizes_ptr: *const i32,
     input_ptr: *const f32,
     output_ptr: *mut f32,
-    layer_count: i32,
+    in_size: i32,
+    out_size: i32,
     batch_count: i32,
     input_stride: i32,
     output_stride: i32,
-    scratch_ptr: *mut f32,
-    scratch_len: i32,
+    activation: i32,
+    thread_id: i32,
+    thread_count: i32,
+    atomics_ptr: *mut i32,
 ) {
     if weights_ptr.is_null()
-        || layer_sizes_ptr.is_null()
         || input_ptr.is_null()
         || output_ptr.is_null()
+        || atomics_ptr.is_null()
     {
         return;
     }
-    if scratch_ptr.is_null() {
-        return;
-    }
-    let layer_count = to_usize(layer_count);
-    if layer_count < 2 {
-        return;
-    }
+    let in_size = to_usize(in_size);
+    let out_size = to_usize(out_size);
     let batch_count = to_usize(batch_count);
     let input_stride = to_usize(input_stride);
     let output_stride = to_usize(output_stride);
-    // Safety: We trust the layer_count passed from JS
-    let layer_sizes = unsafe { core::slice::from_raw_parts(layer_sizes_ptr, layer_count) };
-    let scratch_len = to_usize(scratch_len);
+    let thread_id = to_usize(thread_id);
+    let thread_count = to_usize(thread_count).max(1);
+    let out_limit = if out_size < output_stride {
+        out_size
+    } else {
+        output_stride
+    };
 
-    let mut max_size = 0usize;
-    for &size in layer_sizes {
-        let size = to_usize(size);
-        if size > max_size {
-            max_size = size;
+    // Safety: Pointers are checked generally, but specific bounds are caller's responsibility.
+    unsafe {
+        let mut b = thread_id;
+        while b < batch_count {
+            let input_base = b * input_stride;
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+            let mut w_index = 0usize;
+            for o in 0..out_limit {
+                let sum = dense_dot(weights_ptr.add(w_index), input_ptr.add(input_base), in_size);
+                let bias = *weights_ptr.add(w_index + in_size);
+                w_index += in_size + 1;
+                *output_ptr.add(output_base + o) = apply_activation(activation, sum + bias);
+            }
+            b += thread_count;
         }
+        spin_barrier(atomics_ptr, thread_count as i32);
     }
-    if max_size == 0 {
+}
+
+/// Compute a Dense forward pass for a batch of inputs using Q8_0
+/// block-quantized weights (one `f32` scale + 32 `i8` quants per block,
+/// row-major, each row followed by its `f32` bias).
+///
+/// The input is quantized into `scratch_q_ptr`/`scratch_scale_ptr` once per
+/// batch row and reused across all output neurons.
+///
+/// # Safety
+///
+/// Pointers must be valid for the specified dimensions and strides.
+/// `scratch_q_ptr` must be valid for `q8_block_count(in_size) * Q8_BLOCK_SIZE` writes,
+/// `scratch_scale_ptr` for `q8_block_count(in_size)` writes.
+#[no_mangle]
+pub unsafe extern "C" fn dense_forward_q8(
+    weights_ptr: *const u8,
+    input_ptr: *const f32,
+    output_ptr: *mut f32,
+    in_size: i32,
+    out_size: i32,
+    batch_count: i32,
+    input_stride: i32,
+    output_stride: i32,
+    scratch_q_ptr: *mut i8,
+    scratch_scale_ptr: *mut f32,
+) {
+    if weights_ptr.is_null()
+        || input_ptr.is_null()
+        || output_ptr.is_null()
+        || scratch_q_ptr.is_null()
+        || scratch_scale_ptr.is_null()
+    {
         return;
     }
-    if scratch_len < max_size * 2 {
-        return;
+    let in_size = to_usize(in_size);
+    let out_size = to_usize(out_size);
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let output_stride = to_usize(output_stride);
+    let out_limit = if out_size < output_stride {
+        out_size
+    } else {
+        output_stride
+    };
+    let n_blocks = q8_block_count(in_size);
+    let row_bytes = n_blocks * Q8_BLOCK_BYTES + mem::size_of::<f32>();
+
+    // Safety: Pointers are checked generally, but specific bounds are caller's responsibility.
+    unsafe {
+        for b in 0..batch_count {
+            let input_base = b * input_stride;
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+            quantize_row_q8(
+                input_ptr.add(input_base),
+                in_size,
+                scratch_q_ptr,
+                scratch_scale_ptr,
+            );
+            for o in 0..out_limit {
+                let row_ptr = weights_ptr.add(o * row_bytes);
+                let sum = dense_dot_q8(row_ptr, scratch_q_ptr, scratch_scale_ptr, n_blocks);
+                let bias = *(row_ptr.add(n_blocks * Q8_BLOCK_BYTES) as *const f32);
+                *output_ptr.add(output_base + o) = (sum + bias).tanh();
+            }
+        }
     }
-    // Safety: We trust scratch_len passed from JS
-    let scratch = unsafe { core::slice::from_raw_parts_mut(scratch_ptr, scratch_len) };
-    let (mut cur_buf, mut next_buf) = scratch.split_at_mut(max_size);
+}
+
+/// Compute a Dense forward pass for a batch of inputs using Q4_1
+/// block-quantized weights produced by `quantize_weights_q4_1`.
+///
+/// The input is quantized into the scratch buffers once per batch row and
+/// reused across all output neurons.
+///
+/// # Safety
+///
+/// Pointers must be valid for the specified dimensions and strides.
+/// `scratch_q_ptr` must be valid for `q4_1_block_count(in_size) * Q4_1_BLOCK_SIZE` writes,
+/// `scratch_d_ptr`/`scratch_m_ptr`/`scratch_sum_ptr` for `q4_1_block_count(in_size)` writes each.
+#[no_mangle]
+pub unsafe extern "C" fn dense_forward_q4_1(
+    weights_ptr: *const u8,
+    input_ptr: *const f32,
+    output_ptr: *mut f32,
+    in_size: i32,
+    out_size: i32,
+    batch_count: i32,
+    input_stride: i32,
+    output_stride: i32,
+    scratch_q_ptr: *mut u8,
+    scratch_d_ptr: *mut f32,
+    scratch_m_ptr: *mut f32,
+    scratch_sum_ptr: *mut f32,
+) {
+    if weights_ptr.is_null()
+        || input_ptr.is_null()
+        || output_ptr.is_null()
+        || scratch_q_ptr.is_null()
+        || scratch_d_ptr.is_null()
+        || scratch_m_ptr.is_null()
+        || scratch_sum_ptr.is_null()
+    {
+        return;
+    }
+    let in_size = to_usize(in_size);
+    let out_size = to_usize(out_size);
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let output_stride = to_usize(output_stride);
+    let out_limit = if out_size < output_stride {
+        out_size
+    } else {
+        output_stride
+    };
+    let n_blocks = q4_1_block_count(in_size);
+    let row_bytes = n_blocks * Q4_1_BLOCK_BYTES + mem::size_of::<f32>();
+
+    // Safety: Pointers are checked generally, but specific bounds are caller's responsibility.
+    unsafe {
+        for b in 0..batch_count {
+            let input_base = b * input_stride;
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+            quantize_row_q4_1_unpacked(
+                input_ptr.add(input_base),
+                in_size,
+                scratch_q_ptr,
+                scratch_d_ptr,
+                scratch_m_ptr,
+                scratch_sum_ptr,
+            );
+            for o in 0..out_limit {
+                let row_ptr = weights_ptr.add(o * row_bytes);
+                let sum = dense_dot_q4_1(
+                    row_ptr,
+                    scratch_q_ptr,
+                    scratch_d_ptr,
+                    scratch_m_ptr,
+                    scratch_sum_ptr,
+                    n_blocks,
+                );
+                let bias = *(row_ptr.add(n_blocks * Q4_1_BLOCK_BYTES) as *const f32);
+                *output_ptr.add(output_base + o) = (sum + bias).tanh();
+            }
+        }
+    }
+}
+
+/// Compute an MLP forward pass for a batch of inputs.
+///
+/// `activations_ptr` selects each layer's activation via one of the
+/// `ACTIVATION_*` constants and must point to `layer_count - 1` integers,
+/// one per weight layer in the same order as `layer_sizes_ptr`.
+///
+/// # Safety
+///
+/// Pointers must be valid. `scratch_ptr` must point to sufficient scratch memory.
+/// `layer_sizes_ptr` must point to `layer_count` integers.
+/// `activations_ptr` must point to `layer_count - 1` integers.
+#[no_mangle]
+pub unsafe extern "C" fn mlp_forward(
+    weights_ptr: *const f32,
+    layer_sizes_ptr: *const i32,
+    input_ptr: *const f32,
+    output_ptr: *mut f32,
+    layer_count: i32,
+    batch_count: i32,
+    input_stride: i32,
+    output_stride: i32,
+    scratch_ptr: *mut f32,
+    scratch_len: i32,
+    activations_ptr: *const i32,
+) {
+    if weights_ptr.is_null()
+        || layer_sizes_ptr.is_null()
+        || input_ptr.is_null()
+        || output_ptr.is_null()
+        || activations_ptr.is_null()
+    {
+        return;
+    }
+    if scratch_ptr.is_null() {
+        return;
+    }
+    let layer_count = to_usize(layer_count);
+    if layer_count < 2 {
+        return;
+    }
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let output_stride = to_usize(output_stride);
+    // Safety: We trust the layer_count passed from JS
+    let layer_sizes = unsafe { core::slice::from_raw_parts(layer_sizes_ptr, layer_count) };
+    // Safety: We trust layer_count - 1 activations passed from JS
+    let activations = unsafe { core::slice::from_raw_parts(activations_ptr, layer_count - 1) };
+    let scratch_len = to_usize(scratch_len);
+
+    let mut max_size = 0usize;
+    for &size in layer_sizes {
+        let size = to_usize(size);
+        if size > max_size {
+            max_size = size;
+        }
+    }
+    if max_size == 0 {
+        return;
+    }
+    if scratch_len < max_size * 2 {
+        return;
+    }
+    // Safety: We trust scratch_len passed from JS
+    let scratch = unsafe { core::slice::from_raw_parts_mut(scratch_ptr, scratch_len) };
+    let (mut cur_buf, mut next_buf) = scratch.split_at_mut(max_size);
+
+    // Safety: Main computation loop involving pointer offsets
+    unsafe {
+        for b in 0..batch_count {
+            let input_base = b * input_stride;
+            let input_size = to_usize(layer_sizes[0]);
+            let input_slice = core::slice::from_raw_parts(input_ptr.add(input_base), input_size);
+            cur_buf[..input_size].copy_from_slice(input_slice);
+
+            let mut w_index = 0usize;
+            for l in 0..(layer_count - 1) {
+                let ins = to_usize(layer_sizes[l]);
+                let outs = to_usize(layer_sizes[l + 1]);
+                let activation = activations[l];
+
+                for out_val in next_buf.iter_mut().take(outs) {
+                    let sum = dense_dot(weights_ptr.add(w_index), cur_buf.as_ptr(), ins);
+                    let bias = *weights_ptr.add(w_index + ins);
+                    w_index += ins + 1;
+                    *out_val = apply_activation(activation, sum + bias);
+                }
+                mem::swap(&mut cur_buf, &mut next_buf);
+            }
+            let out_size = to_usize(layer_sizes[layer_count - 1]);
+            let out_limit = if out_size < output_stride {
+                out_size
+            } else {
+                output_stride
+            };
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+
+            for (o, &val) in cur_buf.iter().enumerate().take(out_limit) {
+                *output_ptr.add(output_base + o) = val;
+            }
+        }
+    }
+}
+
+/// Threaded variant of `mlp_forward`: worker `thread_id` (of `thread_count`
+/// total) processes batch indices `thread_id, thread_id + thread_count, ...`,
+/// each writing a disjoint `output_stride`-sized slice of `output_ptr` and
+/// owning its own `max_size * 2` carve-out of `scratch_ptr` (worker `t` uses
+/// the slice starting at `t * max_size * 2`), so no locking is needed beyond
+/// the closing barrier.
+///
+/// # Safety
+///
+/// Same requirements as `mlp_forward`, except `scratch_ptr` must point to at
+/// least `max_size * 2 * thread_count` floats. `thread_id` must be in
+/// `0..thread_count`, and `atomics_ptr` must point to two contiguous `i32`
+/// values shared by and reserved for exactly these `thread_count` workers.
+#[no_mangle]
+pub unsafe extern "C" fn mlp_forward_mt(
+    weights_ptr: *const f32,
+    layer_sizes_ptr: *const i32,
+    input_ptr: *const f32,
+    output_ptr: *mut f32,
+    layer_count: i32,
+    batch_count: i32,
+    input_stride: i32,
+    output_stride: i32,
+    scratch_ptr: *mut f32,
+    scratch_len: i32,
+    activations_ptr: *const i32,
+    thread_id: i32,
+    thread_count: i32,
+    atomics_ptr: *mut i32,
+) {
+    if weights_ptr.is_null()
+        || layer_sizes_ptr.is_null()
+        || input_ptr.is_null()
+        || output_ptr.is_null()
+        || activations_ptr.is_null()
+        || atomics_ptr.is_null()
+    {
+        return;
+    }
+    if scratch_ptr.is_null() {
+        return;
+    }
+    let layer_count = to_usize(layer_count);
+    if layer_count < 2 {
+        return;
+    }
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let output_stride = to_usize(output_stride);
+    let thread_id = to_usize(thread_id);
+    let thread_count = to_usize(thread_count).max(1);
+    // Safety: We trust the layer_count passed from JS
+    let layer_sizes = unsafe { core::slice::from_raw_parts(layer_sizes_ptr, layer_count) };
+    // Safety: We trust layer_count - 1 activations passed from JS
+    let activations = unsafe { core::slice::from_raw_parts(activations_ptr, layer_count - 1) };
+    let scratch_len = to_usize(scratch_len);
+
+    let mut max_size = 0usize;
+    for &size in layer_sizes {
+        let size = to_usize(size);
+        if size > max_size {
+            max_size = size;
+        }
+    }
+    if max_size == 0 {
+        return;
+    }
+    if scratch_len < max_size * 2 * thread_count {
+        return;
+    }
+    // Safety: We trust scratch_len passed from JS
+    let scratch = unsafe { core::slice::from_raw_parts_mut(scratch_ptr, scratch_len) };
+    let worker_scratch = &mut scratch[thread_id * max_size * 2..(thread_id + 1) * max_size * 2];
+    let (mut cur_buf, mut next_buf) = worker_scratch.split_at_mut(max_size);
+
+    // Safety: Main computation loop involving pointer offsets
+    unsafe {
+        let mut b = thread_id;
+        while b < batch_count {
+            let input_base = b * input_stride;
+            let input_size = to_usize(layer_sizes[0]);
+            let input_slice = core::slice::from_raw_parts(input_ptr.add(input_base), input_size);
+            cur_buf[..input_size].copy_from_slice(input_slice);
+
+            let mut w_index = 0usize;
+            for l in 0..(layer_count - 1) {
+                let ins = to_usize(layer_sizes[l]);
+                let outs = to_usize(layer_sizes[l + 1]);
+                let activation = activations[l];
+
+                for out_val in next_buf.iter_mut().take(outs) {
+                    let sum = dense_dot(weights_ptr.add(w_index), cur_buf.as_ptr(), ins);
+                    let bias = *weights_ptr.add(w_index + ins);
+                    w_index += ins + 1;
+                    *out_val = apply_activation(activation, sum + bias);
+                }
+                mem::swap(&mut cur_buf, &mut next_buf);
+            }
+            let out_size = to_usize(layer_sizes[layer_count - 1]);
+            let out_limit = if out_size < output_stride {
+                out_size
+            } else {
+                output_stride
+            };
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+
+            for (o, &val) in cur_buf.iter().enumerate().take(out_limit) {
+                *output_ptr.add(output_base + o) = val;
+            }
+            b += thread_count;
+        }
+        spin_barrier(atomics_ptr, thread_count as i32);
+    }
+}
+
+/// Compute an MLP forward pass for a batch of inputs using Q8_0
+/// block-quantized weights. Each layer's weights are laid out as for
+/// `dense_forward_q8` (blocks followed by an `f32` bias per row),
+/// concatenated layer by layer in the same order as `layer_sizes_ptr`.
+///
+/// # Safety
+///
+/// Pointers must be valid. `layer_sizes_ptr` must point to `layer_count` integers.
+/// `act_scratch_ptr` must point to at least `max_size * 2` floats.
+/// `quant_scratch_ptr` must point to at least `q8_block_count(max_size) * Q8_BLOCK_SIZE` bytes,
+/// `scale_scratch_ptr` to at least `q8_block_count(max_size)` floats.
+#[no_mangle]
+pub unsafe extern "C" fn mlp_forward_q8(
+    weights_ptr: *const u8,
+    layer_sizes_ptr: *const i32,
+    input_ptr: *const f32,
+    output_ptr: *mut f32,
+    layer_count: i32,
+    batch_count: i32,
+    input_stride: i32,
+    output_stride: i32,
+    act_scratch_ptr: *mut f32,
+    act_scratch_len: i32,
+    quant_scratch_ptr: *mut i8,
+    scale_scratch_ptr: *mut f32,
+) {
+    if weights_ptr.is_null()
+        || layer_sizes_ptr.is_null()
+        || input_ptr.is_null()
+        || output_ptr.is_null()
+        || act_scratch_ptr.is_null()
+        || quant_scratch_ptr.is_null()
+        || scale_scratch_ptr.is_null()
+    {
+        return;
+    }
+    let layer_count = to_usize(layer_count);
+    if layer_count < 2 {
+        return;
+    }
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let output_stride = to_usize(output_stride);
+    // Safety: We trust the layer_count passed from JS
+    let layer_sizes = unsafe { core::slice::from_raw_parts(layer_sizes_ptr, layer_count) };
+    let act_scratch_len = to_usize(act_scratch_len);
+
+    let mut max_size = 0usize;
+    for &size in layer_sizes {
+        let size = to_usize(size);
+        if size > max_size {
+            max_size = size;
+        }
+    }
+    if max_size == 0 {
+        return;
+    }
+    if act_scratch_len < max_size * 2 {
+        return;
+    }
+    // Safety: We trust act_scratch_len passed from JS
+    let act_scratch = unsafe { core::slice::from_raw_parts_mut(act_scratch_ptr, act_scratch_len) };
+    let (mut cur_buf, mut next_buf) = act_scratch.split_at_mut(max_size);
+
+    // Safety: Main computation loop involving pointer offsets
+    unsafe {
+        for b in 0..batch_count {
+            let input_base = b * input_stride;
+            let input_size = to_usize(layer_sizes[0]);
+            let input_slice = core::slice::from_raw_parts(input_ptr.add(input_base), input_size);
+            cur_buf[..input_size].copy_from_slice(input_slice);
+
+            let mut w_offset = 0usize;
+            for l in 0..(layer_count - 1) {
+                let ins = to_usize(layer_sizes[l]);
+                let outs = to_usize(layer_sizes[l + 1]);
+                let n_blocks = q8_block_count(ins);
+                let row_bytes = n_blocks * Q8_BLOCK_BYTES + mem::size_of::<f32>();
+
+                quantize_row_q8(cur_buf.as_ptr(), ins, quant_scratch_ptr, scale_scratch_ptr);
+                for (o, out_val) in next_buf.iter_mut().take(outs).enumerate() {
+                    let row_ptr = weights_ptr.add(w_offset + o * row_bytes);
+                    let sum = dense_dot_q8(row_ptr, quant_scratch_ptr, scale_scratch_ptr, n_blocks);
+                    let bias = *(row_ptr.add(n_blocks * Q8_BLOCK_BYTES) as *const f32);
+                    *out_val = (sum + bias).tanh();
+                }
+                w_offset += outs * row_bytes;
+                mem::swap(&mut cur_buf, &mut next_buf);
+            }
+            let out_size = to_usize(layer_sizes[layer_count - 1]);
+            let out_limit = if out_size < output_stride {
+                out_size
+            } else {
+                output_stride
+            };
+            let output_base = b * output_stride;
+            for o in 0..output_stride {
+                *output_ptr.add(output_base + o) = 0.0;
+            }
+
+            for (o, &val) in cur_buf.iter().enumerate().take(out_limit) {
+                *output_ptr.add(output_base + o) = val;
+            }
+        }
+    }
+}
+
+/// Compute a GRU step for a batch of inputs.
+///
+/// # Safety
+///
+/// Pointers must be valid. State pointers must point to buffers of size `hidden_size * batch_count`.
+#[no_mangle]
+pub unsafe extern "C" fn gru_step(
+    weights_ptr: *const f32,
+    input_ptr: *const f32,
+    h_ptr: *mut f32,
+    z_ptr: *mut f32,
+    r_ptr: *mut f32,
+    h_prev_ptr: *mut f32,
+    in_size: i32,
+    hidden_size: i32,
+    batch_count: i32,
+    input_stride: i32,
+) {
+    if weights_ptr.is_null()
+        || input_ptr.is_null()
+        || h_ptr.is_null()
+        || z_ptr.is_null()
+        || r_ptr.is_null()
+        || h_prev_ptr.is_null()
+    {
+        return;
+    }
+    let in_size = to_usize(in_size);
+    let hidden_size = to_usize(hidden_size);
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    if in_size == 0 || hidden_size == 0 || batch_count == 0 {
+        return;
+    }
+    let wsz = hidden_size * in_size;
+    let usz = hidden_size * hidden_size;
+    let wz = 0usize;
+    let wr = wz + wsz;
+    let wh = wr + wsz;
+    let uz = wh + wsz;
+    let ur = uz + usz;
+    let uh = ur + usz;
+    let bz = uh + usz;
+    let br = bz + hidden_size;
+    let bh = br + hidden_size;
 
     // Safety: Main computation loop involving pointer offsets
     unsafe {
         for b in 0..batch_count {
             let input_base = b * input_stride;
-            let input_size = to_usize(layer_sizes[0]);
-            let input_slice = core::slice::from_raw_parts(input_ptr.add(input_base), input_size);
-            cur_buf[..input_size].copy_from_slice(input_slice);
-
-            let mut w_index = 0usize;
-            for l in 0..(layer_count - 1) {
-                let ins = to_usize(layer_sizes[l]);
-                let outs = to_usize(layer_sizes[l + 1]);
-
-                for out_val in next_buf.iter_mut().take(outs) {
-                    let sum = dense_dot(weights_ptr.add(w_index), cur_buf.as_ptr(), ins);
-                    let bias = *weights_ptr.add(w_index + ins);
-                    w_index += ins + 1;
-                    *out_val = (sum + bias).tanh();
-                }
-                mem::swap(&mut cur_buf, &mut next_buf);
+            let state_base = b * hidden_size;
+            for j in 0..hidden_size {
+                *h_prev_ptr.add(state_base + j) = *h_ptr.add(state_base + j);
             }
-            let out_size = to_usize(layer_sizes[layer_count - 1]);
-            let out_limit = if out_size < output_stride {
-                out_size
-            } else {
-                output_stride
-            };
-            let output_base = b * output_stride;
-            for o in 0..output_stride {
-                *output_ptr.add(output_base + o) = 0.0;
+            for j in 0..hidden_size {
+                let wz_row = wz + j * in_size;
+                let wr_row = wr + j * in_size;
+                let uz_row = uz + j * hidden_size;
+                let ur_row = ur + j * hidden_size;
+                let mut sum_z =
+                    dense_dot(weights_ptr.add(wz_row), input_ptr.add(input_base), in_size);
+                let mut sum_r =
+                    dense_dot(weights_ptr.add(wr_row), input_ptr.add(input_base), in_size);
+                sum_z += dense_dot(
+                    weights_ptr.add(uz_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_r += dense_dot(
+                    weights_ptr.add(ur_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_z += *weights_ptr.add(bz + j);
+                sum_r += *weights_ptr.add(br + j);
+                *z_ptr.add(state_base + j) = sigmoid(sum_z);
+                *r_ptr.add(state_base + j) = sigmoid(sum_r);
             }
-
-            for (o, &val) in cur_buf.iter().enumerate().take(out_limit) {
-                *output_ptr.add(output_base + o) = val;
+            for j in 0..hidden_size {
+                let wh_row = wh + j * in_size;
+                let uh_row = uh + j * hidden_size;
+                let mut sum_h =
+                    dense_dot(weights_ptr.add(wh_row), input_ptr.add(input_base), in_size);
+                sum_h += dense_dot_mul(
+                    weights_ptr.add(uh_row),
+                    r_ptr.add(state_base),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_h += *weights_ptr.add(bh + j);
+                let h_tilde = (sum_h).tanh();
+                let z_val = *z_ptr.add(state_base + j);
+                let prev_h = *h_prev_ptr.add(state_base + j);
+                *h_ptr.add(state_base + j) = (1.0 - z_val) * prev_h + z_val * h_tilde;
             }
         }
     }
 }
 
-/// Compute a GRU step for a batch of inputs.
+/// Threaded variant of `gru_step`: worker `thread_id` (of `thread_count`
+/// total) processes batch indices `thread_id, thread_id + thread_count, ...`,
+/// each writing a disjoint `hidden_size`-sized slice of every state buffer,
+/// so no locking is needed beyond the closing barrier.
 ///
 /// # Safety
 ///
-/// Pointers must be valid. State pointers must point to buffers of size `hidden_size * batch_count`.
+/// Same requirements as `gru_step`. `thread_id` must be in `0..thread_count`,
+/// and `atomics_ptr` must point to two contiguous `i32` values shared by
+/// and reserved for exactly these `thread_count` workers.
 #[no_mangle]
-pub unsafe extern "C" fn gru_step(
+pub unsafe extern "C" fn gru_step_mt(
     weights_ptr: *const f32,
     input_ptr: *const f32,
     h_ptr: *mut f32,
@@ -247,6 +1316,9 @@ pub unsafe extern "C" fn gru_step(
     hidden_size: i32,
     batch_count: i32,
     input_stride: i32,
+    thread_id: i32,
+    thread_count: i32,
+    atomics_ptr: *mut i32,
 ) {
     if weights_ptr.is_null()
         || input_ptr.is_null()
@@ -254,6 +1326,7 @@ pub unsafe extern "C" fn gru_step(
         || z_ptr.is_null()
         || r_ptr.is_null()
         || h_prev_ptr.is_null()
+        || atomics_ptr.is_null()
     {
         return;
     }
@@ -261,6 +1334,8 @@ pub unsafe extern "C" fn gru_step(
     let hidden_size = to_usize(hidden_size);
     let batch_count = to_usize(batch_count);
     let input_stride = to_usize(input_stride);
+    let thread_id = to_usize(thread_id);
+    let thread_count = to_usize(thread_count).max(1);
     if in_size == 0 || hidden_size == 0 || batch_count == 0 {
         return;
     }
@@ -278,7 +1353,8 @@ pub unsafe extern "C" fn gru_step(
 
     // Safety: Main computation loop involving pointer offsets
     unsafe {
-        for b in 0..batch_count {
+        let mut b = thread_id;
+        while b < batch_count {
             let input_base = b * input_stride;
             let state_base = b * hidden_size;
             for j in 0..hidden_size {
@@ -325,7 +1401,9 @@ pub unsafe extern "C" fn gru_step(
                 let prev_h = *h_prev_ptr.add(state_base + j);
                 *h_ptr.add(state_base + j) = (1.0 - z_val) * prev_h + z_val * h_tilde;
             }
+            b += thread_count;
         }
+        spin_barrier(atomics_ptr, thread_count as i32);
     }
 }
 
@@ -441,6 +1519,132 @@ pub unsafe extern "C" fn lstm_step(
     }
 }
 
+/// Threaded variant of `lstm_step`: worker `thread_id` (of `thread_count`
+/// total) processes batch indices `thread_id, thread_id + thread_count, ...`,
+/// each writing a disjoint `hidden_size`-sized slice of every state buffer,
+/// so no locking is needed beyond the closing barrier.
+///
+/// # Safety
+///
+/// Same requirements as `lstm_step`. `thread_id` must be in `0..thread_count`,
+/// and `atomics_ptr` must point to two contiguous `i32` values shared by
+/// and reserved for exactly these `thread_count` workers.
+#[no_mangle]
+pub unsafe extern "C" fn lstm_step_mt(
+    weights_ptr: *const f32,
+    input_ptr: *const f32,
+    h_ptr: *mut f32,
+    c_ptr: *mut f32,
+    h_prev_ptr: *mut f32,
+    c_prev_ptr: *mut f32,
+    in_size: i32,
+    hidden_size: i32,
+    batch_count: i32,
+    input_stride: i32,
+    thread_id: i32,
+    thread_count: i32,
+    atomics_ptr: *mut i32,
+) {
+    if weights_ptr.is_null()
+        || input_ptr.is_null()
+        || h_ptr.is_null()
+        || c_ptr.is_null()
+        || h_prev_ptr.is_null()
+        || c_prev_ptr.is_null()
+        || atomics_ptr.is_null()
+    {
+        return;
+    }
+    let in_size = to_usize(in_size);
+    let hidden_size = to_usize(hidden_size);
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let thread_id = to_usize(thread_id);
+    let thread_count = to_usize(thread_count).max(1);
+    if in_size == 0 || hidden_size == 0 || batch_count == 0 {
+        return;
+    }
+    let wsz = hidden_size * in_size;
+    let usz = hidden_size * hidden_size;
+    let wi = 0usize;
+    let wf = wi + wsz;
+    let wo = wf + wsz;
+    let wg = wo + wsz;
+    let ui = wg + wsz;
+    let uf = ui + usz;
+    let uo = uf + usz;
+    let ug = uo + usz;
+    let bi = ug + usz;
+    let bf = bi + hidden_size;
+    let bo = bf + hidden_size;
+    let bg = bo + hidden_size;
+
+    // Safety: Main computation loop involving pointer offsets
+    unsafe {
+        let mut b = thread_id;
+        while b < batch_count {
+            let input_base = b * input_stride;
+            let state_base = b * hidden_size;
+            for j in 0..hidden_size {
+                *h_prev_ptr.add(state_base + j) = *h_ptr.add(state_base + j);
+                *c_prev_ptr.add(state_base + j) = *c_ptr.add(state_base + j);
+            }
+            for j in 0..hidden_size {
+                let wi_row = wi + j * in_size;
+                let wf_row = wf + j * in_size;
+                let wo_row = wo + j * in_size;
+                let wg_row = wg + j * in_size;
+                let ui_row = ui + j * hidden_size;
+                let uf_row = uf + j * hidden_size;
+                let uo_row = uo + j * hidden_size;
+                let ug_row = ug + j * hidden_size;
+                let mut sum_i =
+                    dense_dot(weights_ptr.add(wi_row), input_ptr.add(input_base), in_size);
+                let mut sum_f =
+                    dense_dot(weights_ptr.add(wf_row), input_ptr.add(input_base), in_size);
+                let mut sum_o =
+                    dense_dot(weights_ptr.add(wo_row), input_ptr.add(input_base), in_size);
+                let mut sum_g =
+                    dense_dot(weights_ptr.add(wg_row), input_ptr.add(input_base), in_size);
+                sum_i += dense_dot(
+                    weights_ptr.add(ui_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_f += dense_dot(
+                    weights_ptr.add(uf_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_o += dense_dot(
+                    weights_ptr.add(uo_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_g += dense_dot(
+                    weights_ptr.add(ug_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_i += *weights_ptr.add(bi + j);
+                sum_f += *weights_ptr.add(bf + j);
+                sum_o += *weights_ptr.add(bo + j);
+                sum_g += *weights_ptr.add(bg + j);
+                let i_gate = sigmoid(sum_i);
+                let f_gate = sigmoid(sum_f);
+                let o_gate = sigmoid(sum_o);
+                let g_gate = (sum_g).tanh();
+                let prev_c = *c_prev_ptr.add(state_base + j);
+                let next_c = f_gate * prev_c + i_gate * g_gate;
+                *c_ptr.add(state_base + j) = next_c;
+                *h_ptr.add(state_base + j) = o_gate * (next_c).tanh();
+            }
+            b += thread_count;
+        }
+        spin_barrier(atomics_ptr, thread_count as i32);
+    }
+}
+
 /// Compute an RRU step for a batch of inputs.
 ///
 /// # Safety
@@ -513,3 +1717,94 @@ pub unsafe extern "C" fn rru_step(
         }
     }
 }
+
+/// Threaded variant of `rru_step`: worker `thread_id` (of `thread_count`
+/// total) processes batch indices `thread_id, thread_id + thread_count, ...`,
+/// each writing a disjoint `hidden_size`-sized slice of every state buffer,
+/// so no locking is needed beyond the closing barrier.
+///
+/// # Safety
+///
+/// Same requirements as `rru_step`. `thread_id` must be in `0..thread_count`,
+/// and `atomics_ptr` must point to two contiguous `i32` values shared by
+/// and reserved for exactly these `thread_count` workers.
+#[no_mangle]
+pub unsafe extern "C" fn rru_step_mt(
+    weights_ptr: *const f32,
+    input_ptr: *const f32,
+    h_ptr: *mut f32,
+    h_prev_ptr: *mut f32,
+    in_size: i32,
+    hidden_size: i32,
+    batch_count: i32,
+    input_stride: i32,
+    thread_id: i32,
+    thread_count: i32,
+    atomics_ptr: *mut i32,
+) {
+    if weights_ptr.is_null()
+        || input_ptr.is_null()
+        || h_ptr.is_null()
+        || h_prev_ptr.is_null()
+        || atomics_ptr.is_null()
+    {
+        return;
+    }
+    let in_size = to_usize(in_size);
+    let hidden_size = to_usize(hidden_size);
+    let batch_count = to_usize(batch_count);
+    let input_stride = to_usize(input_stride);
+    let thread_id = to_usize(thread_id);
+    let thread_count = to_usize(thread_count).max(1);
+    if in_size == 0 || hidden_size == 0 || batch_count == 0 {
+        return;
+    }
+    let wsz = hidden_size * in_size;
+    let usz = hidden_size * hidden_size;
+    let wc = 0usize;
+    let wr = wc + wsz;
+    let uc = wr + wsz;
+    let ur = uc + usz;
+    let bc = ur + usz;
+    let br = bc + hidden_size;
+
+    // Safety: Main computation loop involving pointer offsets
+    unsafe {
+        let mut b = thread_id;
+        while b < batch_count {
+            let input_base = b * input_stride;
+            let state_base = b * hidden_size;
+            for j in 0..hidden_size {
+                *h_prev_ptr.add(state_base + j) = *h_ptr.add(state_base + j);
+            }
+            for j in 0..hidden_size {
+                let wc_row = wc + j * in_size;
+                let wr_row = wr + j * in_size;
+                let uc_row = uc + j * hidden_size;
+                let ur_row = ur + j * hidden_size;
+                let mut sum_c =
+                    dense_dot(weights_ptr.add(wc_row), input_ptr.add(input_base), in_size);
+                let mut sum_r =
+                    dense_dot(weights_ptr.add(wr_row), input_ptr.add(input_base), in_size);
+                sum_c += dense_dot(
+                    weights_ptr.add(uc_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_r += dense_dot(
+                    weights_ptr.add(ur_row),
+                    h_prev_ptr.add(state_base),
+                    hidden_size,
+                );
+                sum_c += *weights_ptr.add(bc + j);
+                sum_r += *weights_ptr.add(br + j);
+                let cand = (sum_c).tanh();
+                let gate = sigmoid(sum_r);
+                let prev = *h_prev_ptr.add(state_base + j);
+                *h_ptr.add(state_base + j) = (1.0 - gate) * prev + gate * cand;
+            }
+            b += thread_count;
+        }
+        spin_barrier(atomics_ptr, thread_count as i32);
+    }
+}